@@ -0,0 +1,64 @@
+use std::borrow::Cow;
+
+/// A byte buffer that tracks whether it currently holds compressed or
+/// decompressed data, along with the decompressed length when compressed.
+#[derive(Default)]
+pub(crate) struct CompressableBytes<'bytes> {
+    bytes: Cow<'bytes, [u8]>,
+    decompressed_len: Option<usize>,
+}
+
+impl<'bytes> CompressableBytes<'bytes> {
+    // Constructed by archive/file parsing outside this snapshot; exercised
+    // here only by chunk.rs's unit tests.
+    #[allow(dead_code)]
+    #[must_use]
+    pub(crate) fn from_decompressed(bytes: impl Into<Cow<'bytes, [u8]>>) -> Self {
+        Self {
+            bytes: bytes.into(),
+            decompressed_len: None,
+        }
+    }
+
+    #[allow(dead_code)]
+    #[must_use]
+    pub(crate) fn from_compressed(
+        bytes: impl Into<Cow<'bytes, [u8]>>,
+        decompressed_len: usize,
+    ) -> Self {
+        Self {
+            bytes: bytes.into(),
+            decompressed_len: Some(decompressed_len),
+        }
+    }
+
+    #[must_use]
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    #[must_use]
+    pub(crate) fn is_compressed(&self) -> bool {
+        self.decompressed_len.is_some()
+    }
+
+    #[must_use]
+    pub(crate) fn is_decompressed(&self) -> bool {
+        self.decompressed_len.is_none()
+    }
+
+    #[must_use]
+    pub(crate) fn decompressed_len(&self) -> Option<usize> {
+        self.decompressed_len
+    }
+
+    #[must_use]
+    pub(crate) fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    #[must_use]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+}
@@ -0,0 +1,37 @@
+macro_rules! compressable_bytes {
+    ($this:ident) => {
+        impl<'bytes> $this<'bytes> {
+            #[must_use]
+            pub fn as_bytes(&self) -> &[u8] {
+                self.bytes.as_bytes()
+            }
+
+            #[must_use]
+            pub fn is_compressed(&self) -> bool {
+                self.bytes.is_compressed()
+            }
+
+            #[must_use]
+            pub fn is_decompressed(&self) -> bool {
+                self.bytes.is_decompressed()
+            }
+
+            #[must_use]
+            pub fn decompressed_len(&self) -> Option<usize> {
+                self.bytes.decompressed_len()
+            }
+
+            #[must_use]
+            pub fn len(&self) -> usize {
+                self.bytes.len()
+            }
+
+            #[must_use]
+            pub fn is_empty(&self) -> bool {
+                self.bytes.is_empty()
+            }
+        }
+    };
+}
+
+pub(crate) use compressable_bytes;
@@ -9,7 +9,9 @@ use flate2::{
     Compress, Compression,
 };
 use lzzzz::{lz4, lz4_hc};
-use std::io::Write;
+use rayon::prelude::*;
+use std::{io::Write, sync::Arc};
+use xxhash_rust::xxh3;
 
 #[repr(transparent)]
 pub struct OptionsBuilder(Options);
@@ -32,6 +34,18 @@ impl OptionsBuilder {
         self
     }
 
+    #[must_use]
+    pub fn codec(mut self, codec: Arc<dyn Codec>) -> Self {
+        self.0.codec = Some(codec);
+        self
+    }
+
+    #[must_use]
+    pub fn verify(mut self, verify: bool) -> Self {
+        self.0.verify = verify;
+        self
+    }
+
     #[must_use]
     pub fn new() -> Self {
         Self::default()
@@ -43,14 +57,33 @@ impl Default for OptionsBuilder {
         Self(Options {
             compression_format: CompressionFormat::default(),
             compression_level: CompressionLevel::default(),
+            codec: None,
+            verify: false,
         })
     }
 }
 
-#[derive(Clone, Copy)]
+/// A pluggable (de)compressor that `Options` can use in place of the built-in
+/// zlib/lz4/zstd implementations selected by `CompressionFormat`.
+pub trait Codec: Send + Sync {
+    /// Compresses `input`, appending the result to `out`.
+    fn compress(&self, input: &[u8], out: &mut Vec<u8>) -> Result<()>;
+
+    /// Decompresses `input`, appending the result to `out`. `out` may
+    /// already contain unrelated bytes from a reused buffer, so append
+    /// rather than overwrite, and return only the number of bytes written
+    /// by *this* call, not `out.len()` — `decompress_into` uses that count
+    /// to locate the newly-written tail for both its size check and its
+    /// `Options::verify` checksum.
+    fn decompress(&self, input: &[u8], out: &mut Vec<u8>) -> Result<usize>;
+}
+
+#[derive(Clone)]
 pub struct Options {
     compression_format: CompressionFormat,
     compression_level: CompressionLevel,
+    codec: Option<Arc<dyn Codec>>,
+    verify: bool,
 }
 
 impl Options {
@@ -68,6 +101,16 @@ impl Options {
     pub fn compression_level(&self) -> CompressionLevel {
         self.compression_level
     }
+
+    #[must_use]
+    pub fn verify(&self) -> bool {
+        self.verify
+    }
+
+    #[must_use]
+    pub fn codec(&self) -> Option<&Arc<dyn Codec>> {
+        self.codec.as_ref()
+    }
 }
 
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
@@ -94,6 +137,13 @@ impl From<DX10> for Extra {
 pub struct Chunk<'bytes> {
     pub(crate) bytes: CompressableBytes<'bytes>,
     pub extra: Extra,
+    /// Expected xxHash3 of the decompressed payload, checked by
+    /// `decompress_into` when `Options::verify` is enabled.
+    pub checksum: Option<u64>,
+}
+
+pub(crate) fn checksum(bytes: &[u8]) -> u64 {
+    xxh3::xxh3_64(bytes)
 }
 
 derive::compressable_bytes!(Chunk);
@@ -102,6 +152,8 @@ impl<'bytes> Chunk<'bytes> {
     pub fn compress_into(&self, out: &mut Vec<u8>, options: &Options) -> Result<()> {
         if self.is_compressed() {
             Err(Error::AlreadyCompressed)
+        } else if let Some(codec) = options.codec() {
+            codec.compress(self.as_bytes(), out)
         } else {
             match options.compression_format {
                 CompressionFormat::Zip => match options.compression_level {
@@ -112,42 +164,92 @@ impl<'bytes> Chunk<'bytes> {
                         self.compress_into_zlib(out, Compression::best(), 12)
                     }
                     CompressionLevel::SF => self.compress_into_zlib(out, Compression::best(), 15),
+                    CompressionLevel::Custom { zlib, .. } => {
+                        self.compress_into_zlib(out, Compression::new(zlib), 15)
+                    }
                 },
-                CompressionFormat::LZ4 => self.compress_into_lz4(out),
+                CompressionFormat::LZ4 => self.compress_into_lz4(out, options.compression_level),
+                CompressionFormat::Zstd => self.compress_into_zstd(out, options.compression_level),
             }
         }
     }
 
+    /// Compresses each chunk in `chunks` across a thread pool, returning each
+    /// chunk's output buffer in the same order as the input slice.
+    pub fn compress_many_into(chunks: &[Self], options: &Options) -> Result<Vec<Vec<u8>>> {
+        chunks
+            .par_iter()
+            .map(|chunk| {
+                let mut out = Vec::new();
+                chunk.compress_into(&mut out, options)?;
+                Ok(out)
+            })
+            .collect()
+    }
+
     pub fn decompress_into(&self, out: &mut Vec<u8>, options: &Options) -> Result<()> {
         let Some(decompressed_len) = self.decompressed_len() else {
             return Err(Error::AlreadyDecompressed);
         };
 
         out.reserve_exact(decompressed_len);
-        let out_len = match options.compression_format {
-            CompressionFormat::Zip => self.decompress_into_zlib(out),
-            CompressionFormat::LZ4 => self.decompress_into_lz4(out),
+        let out_len = if let Some(codec) = options.codec() {
+            codec.decompress(self.as_bytes(), out)
+        } else {
+            match options.compression_format {
+                CompressionFormat::Zip => self.decompress_into_zlib(out),
+                CompressionFormat::LZ4 => self.decompress_into_lz4(out),
+                CompressionFormat::Zstd => self.decompress_into_zstd(out),
+            }
         }?;
 
-        if out_len == decompressed_len {
-            Ok(())
-        } else {
-            Err(Error::DecompressionSizeMismatch {
+        if out_len != decompressed_len {
+            return Err(Error::DecompressionSizeMismatch {
                 expected: decompressed_len,
                 actual: out_len,
-            })
+            });
+        }
+
+        if options.verify() {
+            if let Some(expected) = self.checksum {
+                let actual = checksum(&out[out.len() - out_len..]);
+                if actual != expected {
+                    return Err(Error::ChecksumMismatch { expected, actual });
+                }
+            }
         }
+
+        Ok(())
     }
 
+    // Constructed by archive/directory parsing outside this snapshot.
+    #[allow(dead_code)]
     pub(crate) fn from_bytes(bytes: CompressableBytes<'_>) -> Chunk<'_> {
         Chunk {
             bytes,
             extra: Extra::default(),
+            checksum: None,
         }
     }
 
-    fn compress_into_lz4(&self, out: &mut Vec<u8>) -> Result<()> {
-        lz4_hc::compress_to_vec(self.as_bytes(), out, lz4_hc::CLEVEL_MAX)?;
+    /// Computes the checksum of the chunk's current (decompressed) payload
+    /// and attaches it, so that a later `decompress_into` with
+    /// `Options::verify(true)` has something to check against. Must be
+    /// called while the chunk still holds decompressed bytes.
+    #[must_use]
+    pub fn with_checksum(mut self) -> Self {
+        self.checksum = Some(checksum(self.as_bytes()));
+        self
+    }
+
+    fn compress_into_lz4(&self, out: &mut Vec<u8>, level: CompressionLevel) -> Result<()> {
+        let level = match level {
+            CompressionLevel::Custom { lz4_hc, .. } => lz4_hc,
+            CompressionLevel::FO4 | CompressionLevel::FO4Xbox | CompressionLevel::SF => {
+                lz4_hc::CLEVEL_MAX
+            }
+        };
+        lz4_hc::compress_to_vec(self.as_bytes(), out, level)?;
         Ok(())
     }
 
@@ -171,6 +273,25 @@ impl<'bytes> Chunk<'bytes> {
         Ok(len)
     }
 
+    fn compress_into_zstd(&self, out: &mut Vec<u8>, level: CompressionLevel) -> Result<()> {
+        let level = match level {
+            CompressionLevel::FO4 | CompressionLevel::SF => zstd::DEFAULT_COMPRESSION_LEVEL,
+            // Xbox's zlib preset trades ratio for a smaller memory
+            // footprint; mirror that intent here instead of reusing zstd's
+            // most memory-hungry level.
+            CompressionLevel::FO4Xbox => 1,
+            CompressionLevel::Custom { zstd, .. } => zstd,
+        };
+        zstd::stream::copy_encode(self.as_bytes(), out, level)?;
+        Ok(())
+    }
+
+    fn decompress_into_zstd(&self, out: &mut Vec<u8>) -> Result<usize> {
+        let start = out.len();
+        zstd::stream::copy_decode(self.as_bytes(), &mut *out)?;
+        Ok(out.len() - start)
+    }
+
     fn decompress_into_zlib(&self, out: &mut Vec<u8>) -> Result<usize> {
         let mut d = ZlibDecoder::new(out);
         d.write_all(self.as_bytes())?;
@@ -180,7 +301,11 @@ impl<'bytes> Chunk<'bytes> {
 
 #[cfg(test)]
 mod tests {
-    use super::{Chunk, Extra};
+    use super::{
+        checksum, Chunk, Codec, CompressableBytes, CompressionFormat, CompressionLevel, Error,
+        Extra, Options, Result,
+    };
+    use std::sync::Arc;
 
     #[test]
     fn default_state() {
@@ -191,4 +316,248 @@ mod tests {
         assert_eq!(c.len(), 0);
         assert_eq!(c.extra, Extra::GNRL);
     }
+
+    fn make_chunk(bytes: Vec<u8>) -> Chunk<'static> {
+        Chunk {
+            bytes: CompressableBytes::from_decompressed(bytes),
+            extra: Extra::default(),
+            checksum: None,
+        }
+    }
+
+    #[test]
+    fn zstd_round_trips() {
+        let data = b"the quick brown fox jumps over the lazy dog".repeat(4);
+        let chunk = make_chunk(data.clone());
+        let options = Options::builder()
+            .compression_format(CompressionFormat::Zstd)
+            .build();
+
+        let mut compressed = Vec::new();
+        chunk.compress_into(&mut compressed, &options).unwrap();
+
+        let compressed_chunk = Chunk {
+            bytes: CompressableBytes::from_compressed(compressed, data.len()),
+            extra: Extra::default(),
+            checksum: None,
+        };
+        let mut decompressed = Vec::new();
+        compressed_chunk
+            .decompress_into(&mut decompressed, &options)
+            .unwrap();
+        assert_eq!(decompressed, data);
+    }
+
+    #[test]
+    fn zstd_round_trips_with_xbox_and_custom_levels() {
+        let data = b"the quick brown fox jumps over the lazy dog".repeat(4);
+
+        for level in [
+            CompressionLevel::FO4Xbox,
+            CompressionLevel::Custom {
+                zlib: 0,
+                lz4_hc: 0,
+                zstd: 19,
+            },
+        ] {
+            let chunk = make_chunk(data.clone());
+            let options = Options::builder()
+                .compression_format(CompressionFormat::Zstd)
+                .compression_level(level)
+                .build();
+
+            let mut compressed = Vec::new();
+            chunk.compress_into(&mut compressed, &options).unwrap();
+
+            let compressed_chunk = Chunk {
+                bytes: CompressableBytes::from_compressed(compressed, data.len()),
+                extra: Extra::default(),
+                checksum: None,
+            };
+            let mut decompressed = Vec::new();
+            compressed_chunk
+                .decompress_into(&mut decompressed, &options)
+                .unwrap();
+            assert_eq!(decompressed, data);
+        }
+    }
+
+    #[test]
+    fn custom_zlib_level_changes_output() {
+        let data = b"a".repeat(4096);
+        let chunk = make_chunk(data);
+
+        let low = Options::builder()
+            .compression_format(CompressionFormat::Zip)
+            .compression_level(CompressionLevel::Custom { zlib: 0, lz4_hc: 0, zstd: 1 })
+            .build();
+        let high = Options::builder()
+            .compression_format(CompressionFormat::Zip)
+            .compression_level(CompressionLevel::Custom { zlib: 9, lz4_hc: 0, zstd: 1 })
+            .build();
+
+        let mut low_out = Vec::new();
+        chunk.compress_into(&mut low_out, &low).unwrap();
+        let mut high_out = Vec::new();
+        chunk.compress_into(&mut high_out, &high).unwrap();
+
+        assert_ne!(low_out, high_out);
+    }
+
+    #[test]
+    fn custom_lz4_level_compresses() {
+        let data = b"hello world, hello world, hello world!".repeat(8);
+        let chunk = make_chunk(data);
+        let options = Options::builder()
+            .compression_format(CompressionFormat::LZ4)
+            .compression_level(CompressionLevel::Custom { zlib: 0, lz4_hc: 3, zstd: 1 })
+            .build();
+
+        let mut out = Vec::new();
+        chunk.compress_into(&mut out, &options).unwrap();
+        assert!(!out.is_empty());
+    }
+
+    #[test]
+    fn compress_many_into_preserves_order() {
+        let options = Options::builder()
+            .compression_format(CompressionFormat::Zstd)
+            .build();
+        let chunks: Vec<_> = (0..4u8).map(|i| make_chunk(vec![i; 64])).collect();
+
+        let compressed = Chunk::compress_many_into(&chunks, &options).unwrap();
+        assert_eq!(compressed.len(), chunks.len());
+
+        for (chunk, out) in chunks.iter().zip(compressed.iter()) {
+            let mut expected = Vec::new();
+            chunk.compress_into(&mut expected, &options).unwrap();
+            assert_eq!(*out, expected);
+        }
+    }
+
+    struct UppercaseCodec;
+
+    impl Codec for UppercaseCodec {
+        fn compress(&self, input: &[u8], out: &mut Vec<u8>) -> Result<()> {
+            out.extend(input.iter().map(u8::to_ascii_uppercase));
+            Ok(())
+        }
+
+        fn decompress(&self, input: &[u8], out: &mut Vec<u8>) -> Result<usize> {
+            out.extend_from_slice(input);
+            Ok(input.len())
+        }
+    }
+
+    #[test]
+    fn codec_override_replaces_builtin_dispatch() {
+        let data = b"hello".to_vec();
+        let chunk = make_chunk(data.clone());
+        let options = Options::builder().codec(Arc::new(UppercaseCodec)).build();
+
+        let mut compressed = Vec::new();
+        chunk.compress_into(&mut compressed, &options).unwrap();
+        assert_eq!(compressed, b"HELLO");
+
+        let compressed_chunk = Chunk {
+            bytes: CompressableBytes::from_compressed(compressed, data.len()),
+            extra: Extra::default(),
+            checksum: None,
+        };
+        let mut decompressed = Vec::new();
+        compressed_chunk
+            .decompress_into(&mut decompressed, &options)
+            .unwrap();
+        assert_eq!(decompressed, b"HELLO");
+    }
+
+    #[test]
+    fn codec_decompress_appends_to_a_reused_buffer() {
+        let data = b"hello".to_vec();
+        let chunk = make_chunk(data.clone());
+        let options = Options::builder().codec(Arc::new(UppercaseCodec)).build();
+
+        let mut compressed = Vec::new();
+        chunk.compress_into(&mut compressed, &options).unwrap();
+
+        let compressed_chunk = Chunk {
+            bytes: CompressableBytes::from_compressed(compressed, data.len()),
+            extra: Extra::default(),
+            checksum: None,
+        };
+        let mut out = b"stale leading bytes".to_vec();
+        compressed_chunk.decompress_into(&mut out, &options).unwrap();
+        assert!(out.ends_with(b"HELLO"));
+    }
+
+    #[test]
+    fn verify_detects_checksum_mismatch() {
+        let data = b"integrity please".to_vec();
+        let chunk = make_chunk(data.clone());
+        let options = Options::builder()
+            .compression_format(CompressionFormat::Zstd)
+            .verify(true)
+            .build();
+
+        let mut compressed = Vec::new();
+        chunk.compress_into(&mut compressed, &options).unwrap();
+
+        let bad_chunk = Chunk {
+            bytes: CompressableBytes::from_compressed(compressed, data.len()),
+            extra: Extra::default(),
+            checksum: Some(checksum(b"not the right bytes")),
+        };
+        let mut out = Vec::new();
+        let err = bad_chunk.decompress_into(&mut out, &options).unwrap_err();
+        assert!(matches!(err, Error::ChecksumMismatch { .. }));
+    }
+
+    #[test]
+    fn with_checksum_populates_real_construction_path() {
+        let data = b"checksum me please".to_vec();
+        let chunk = make_chunk(data.clone()).with_checksum();
+        assert_eq!(chunk.checksum, Some(checksum(&data)));
+
+        let options = Options::builder()
+            .compression_format(CompressionFormat::Zstd)
+            .verify(true)
+            .build();
+        let mut compressed = Vec::new();
+        chunk.compress_into(&mut compressed, &options).unwrap();
+
+        let compressed_chunk = Chunk {
+            bytes: CompressableBytes::from_compressed(compressed, data.len()),
+            extra: Extra::default(),
+            checksum: chunk.checksum,
+        };
+        let mut decompressed = Vec::new();
+        compressed_chunk
+            .decompress_into(&mut decompressed, &options)
+            .unwrap();
+        assert_eq!(decompressed, data);
+    }
+
+    #[test]
+    fn verify_hashes_only_newly_decompressed_bytes() {
+        let data = b"fresh payload".to_vec();
+        let chunk = make_chunk(data.clone());
+        let options = Options::builder()
+            .compression_format(CompressionFormat::Zstd)
+            .verify(true)
+            .build();
+
+        let mut compressed = Vec::new();
+        chunk.compress_into(&mut compressed, &options).unwrap();
+
+        let compressed_chunk = Chunk {
+            bytes: CompressableBytes::from_compressed(compressed, data.len()),
+            extra: Extra::default(),
+            checksum: Some(checksum(&data)),
+        };
+        // A reused, non-empty output buffer should not affect the checksum:
+        // only the freshly-appended tail must be hashed.
+        let mut out = b"stale leading bytes".to_vec();
+        compressed_chunk.decompress_into(&mut out, &options).unwrap();
+        assert!(out.ends_with(&data));
+    }
 }
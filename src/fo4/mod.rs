@@ -0,0 +1,55 @@
+pub mod chunk;
+
+pub use chunk::{Chunk, Codec, Extra, Options, OptionsBuilder, DX10};
+
+use std::num::TryFromIntError;
+use thiserror::Error as ThisError;
+
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum CompressionFormat {
+    #[default]
+    Zip,
+    LZ4,
+    Zstd,
+}
+
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum CompressionLevel {
+    #[default]
+    FO4,
+    FO4Xbox,
+    SF,
+    /// Per-format overrides: `zlib` is a 0..=9 `flate2::Compression` level,
+    /// `lz4_hc` an `lzzzz::lz4_hc` level, and `zstd` a 1..=22 zstd level.
+    /// Each is only consulted by the matching `CompressionFormat`.
+    Custom { zlib: u32, lz4_hc: i32, zstd: i32 },
+}
+
+#[non_exhaustive]
+#[derive(Debug, ThisError)]
+pub enum Error {
+    #[error("chunk is already compressed")]
+    AlreadyCompressed,
+
+    #[error("chunk is already decompressed")]
+    AlreadyDecompressed,
+
+    #[error("decompression produced {actual} bytes, expected {expected}")]
+    DecompressionSizeMismatch { expected: usize, actual: usize },
+
+    #[error("checksum mismatch: expected {expected:#x}, got {actual:#x}")]
+    ChecksumMismatch { expected: u64, actual: u64 },
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    TryFromInt(#[from] TryFromIntError),
+
+    #[error(transparent)]
+    Lz4(#[from] lzzzz::Error),
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
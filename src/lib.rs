@@ -0,0 +1,3 @@
+mod containers;
+mod derive;
+pub mod fo4;